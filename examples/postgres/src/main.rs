@@ -27,10 +27,14 @@ async fn main() {
 }
 
 async fn greet(session: SessionPgSession) -> String {
-    let mut count: usize = session.get("count").unwrap_or(0);
-
-    count += 1;
-    session.set("count", count);
+    let mut count = 0usize;
+
+    // `update` reads, mutates and writes "count" back under a single lock, so two
+    // concurrent requests can't both read the old value and clobber each other.
+    session.update("count", |current: &mut usize| {
+        *current += 1;
+        count = *current;
+    });
 
     count.to_string()
 }
@@ -0,0 +1,146 @@
+use crate::{DatabasePool, Session, SessionStore};
+use cookie::{time, Cookie, CookieJar, SameSite};
+use http::{header::SET_COOKIE, HeaderValue, Request, Response};
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A Tower [`Layer`] that installs a [`Session`] into every request's extensions and, once
+/// the inner service has responded, writes the `Set-Cookie` header for it.
+///
+/// This is also what makes [`Session::regenerate`]/[`Session::renew`] safe: the `Session` a
+/// handler receives is a clone taken out of the request's extensions by
+/// [`Session::from_request_parts`](Session), so mutating its `id` never reaches this layer's
+/// own, separately-held clone. Instead this layer remembers the id the Session was
+/// *extracted* under, and after the inner service returns resolves it through
+/// [`SessionStore::resolve_id`] — which follows the redirect `regenerate`/`renew` leave
+/// behind at the old id — before writing the cookie.
+///
+/// The cookie is written `HttpOnly`, `Secure` and `SameSite=Lax`, and carries a `Max-Age`
+/// matching the resolved Session's `expires_at`, so the client's copy tracks the server-side
+/// (possibly sliding) expiry instead of outliving it as a bare session cookie.
+#[derive(Debug, Clone)]
+pub struct SessionLayer<T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    store: SessionStore<T>,
+}
+
+impl<T> SessionLayer<T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    /// Creates a new `SessionLayer` backed by `store`.
+    ///
+    /// If `store`'s [`SessionConfig::with_purge_interval`](crate::SessionConfig) was set,
+    /// this also spawns a background task that calls
+    /// [`SessionStore::cleanup_expired`] on that interval for as long as the process runs,
+    /// so expired Sessions are purged without the application having to remember to do so
+    /// itself.
+    pub fn new(store: SessionStore<T>) -> Self {
+        if let Some(interval) = store.config.purge_interval {
+            let reaper_store = store.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    let _ = reaper_store.cleanup_expired().await;
+                }
+            });
+        }
+
+        Self { store }
+    }
+}
+
+impl<S, T> Layer<S> for SessionLayer<T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    type Service = SessionService<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionService {
+            inner,
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// The [`Service`] produced by [`SessionLayer`]. See its docs for what it does.
+#[derive(Debug, Clone)]
+pub struct SessionService<S, T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    inner: S,
+    store: SessionStore<T>,
+}
+
+impl<S, T, ReqBody, ResBody> Service<Request<ReqBody>> for SessionService<S, T>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let store = self.store.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let cookies = CookieJar::from_headers(req.headers());
+            let session = Session::new(&store, &cookies).await;
+            // Captured before the handler runs: the id the Session was extracted under, and
+            // therefore the id the request's cookie is currently set to. `regenerate`/`renew`
+            // move the Session on during the handler, but never touch this local copy.
+            let extracted_id = session.get_session_id().await.inner();
+
+            req.extensions_mut().insert(session);
+
+            let mut response = inner.call(req).await?;
+
+            // Follow any redirect `regenerate`/`renew` left behind at `extracted_id` to find
+            // where the Session actually ended up, persist it there, then point the cookie
+            // at the final id and clean up the now-unneeded redirect.
+            let final_id = store.resolve_id(extracted_id);
+            let _ = store.save(final_id).await;
+            store.evict_redirect(extracted_id);
+
+            let mut cookie = Cookie::new(store.config.cookie_name.clone(), final_id.to_string());
+            cookie.set_http_only(true);
+            cookie.set_same_site(SameSite::Lax);
+            cookie.set_secure(true);
+            if let Some(expires_at) = store.expires_at(final_id) {
+                if let Ok(max_age) = expires_at.duration_since(SystemTime::now()) {
+                    cookie.set_max_age(Some(
+                        time::Duration::try_from(max_age).unwrap_or(time::Duration::ZERO),
+                    ));
+                } else {
+                    cookie.set_max_age(Some(time::Duration::ZERO));
+                }
+            }
+
+            if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+                response.headers_mut().append(SET_COOKIE, value);
+            }
+
+            Ok(response)
+        })
+    }
+}
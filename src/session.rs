@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use axum_core::extract::FromRequestParts;
 use cookie::CookieJar;
 use http::{self, request::Parts, StatusCode};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use std::{
     convert::From,
     fmt::Debug,
@@ -58,6 +58,12 @@ where
             None => Self::generate_uuid(store).await,
         };
 
+        // Materializes the in-memory entry this Session's `get`/`set`/`save`/... all operate
+        // on, and (under `refresh_on_access`) bumps its sliding expiry. Without this, a
+        // brand new id — or one from a cookie whose entry this process never loaded before —
+        // has no entry for any of those calls to find.
+        store.ensure_entry(id.inner()).await;
+
         Self {
             id,
             store: store.clone(),
@@ -65,45 +71,63 @@ where
     }
 
     pub(crate) async fn generate_uuid(store: &SessionStore<S>) -> SessionID {
-        loop {
-            let token = Uuid::new_v4();
-
-            if !store.inner.contains_key(&token.to_string()) {
-                //This fixes an already used but in database issue.
-                if let Some(client) = &store.client {
-                    // Unwrap should be safe to use as we would want it to crash if there was a major database error.
-                    // This would mean the database no longer is online or the table missing etc.
-                    if !client
-                        .exists(&token.to_string(), &store.config.table_name)
-                        .await
-                        .unwrap()
-                    {
-                        return SessionID(token);
-                    }
-                } else {
-                    return SessionID(token);
-                }
-            }
-        }
+        SessionID(store.generate_unique_id().await)
     }
 
-    /// Sets the Session to renew its Session ID.
-    /// This Deletes Session data from the database
-    /// associated with the old key. This helps to enhance
-    /// Security when logging into Secure area's across a website.
+    /// Renews the Session's ID, discarding the old key's data and deleting its database row.
+    /// This helps to enhance Security when logging into Secure area's across a website.
+    ///
+    /// Updates `self`'s own id, so the Session handle points at the new id for the rest of
+    /// the request. A redirect is left behind at the old id so a response-time cookie
+    /// rewrite (which still holds the pre-renew id) can resolve to the new one.
     ///
     /// # Examples
     /// ```rust ignore
-    /// session.renew();
+    /// session.renew().await;
     /// ```
     ///
     #[inline]
-    pub fn renew(&self) {
-        self.store.renew(self.id.inner());
+    pub async fn renew(&mut self) {
+        let new_id = self.store.renew(self.id.inner()).await;
+        self.id = SessionID(new_id);
+    }
+
+    /// Rotates the Session's ID while preserving its stored data.
+    ///
+    /// Unlike [`renew`](Session::renew), which discards the old key's data, `regenerate`
+    /// allocates a fresh Session ID, moves the current data across to it, and deletes the
+    /// old row/key once the move is complete. This is the standard defense against session
+    /// fixation: call it whenever a Session's privilege level changes, e.g. right after a
+    /// successful login, so an attacker who obtained the pre-login ID cannot ride along
+    /// with the now-authenticated Session.
+    ///
+    /// Updates `self`'s own id immediately, so any further calls on this Session within the
+    /// same handler (`get`, `set`, ...) operate on the new id. The `Session` the handler
+    /// holds, though, is only one of potentially several clones extracted from the request's
+    /// extensions — the [`SessionLayer`](crate::SessionLayer) holds its own, taken before the
+    /// handler ran, and that clone's `id` field does *not* see this mutation. To get the
+    /// rotated id into the response cookie anyway, `SessionStore::regenerate` leaves a
+    /// redirect behind at the old id; the layer resolves it via
+    /// [`SessionStore::resolve_id`](crate::SessionStore::resolve_id) after the handler
+    /// returns, using the pre-regenerate id it captured at extraction time, and writes the
+    /// resolved id into the `Set-Cookie` header.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.regenerate().await;
+    /// ```
+    ///
+    #[inline]
+    pub async fn regenerate(&mut self) {
+        let new_id = self.store.regenerate(self.id.inner()).await;
+        self.id = SessionID(new_id);
     }
 
     /// Sets the Current Session to be Destroyed on the next run.
     ///
+    /// Clears the Session's in-memory data immediately; its database row (if any) is deleted
+    /// and the entry dropped once the request finishes, instead of being persisted.
+    ///
     /// # Examples
     /// ```rust ignore
     /// session.destroy();
@@ -141,6 +165,23 @@ where
         self.store.set_store(self.id.inner(), storable);
     }
 
+    /// Resets the Session's expiry to now plus [`SessionConfig::with_max_age`](crate::SessionConfig),
+    /// as if it had just been loaded under sliding expiration.
+    ///
+    /// This is the same bump [`SessionConfig::with_refresh_on_access`](crate::SessionConfig)
+    /// applies on every load, so it extends a Session's expiry on demand when that automatic
+    /// per-request refresh is off, e.g. right after a "remember me" checkbox is ticked.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.refresh().await;
+    /// ```
+    ///
+    #[inline]
+    pub async fn refresh(&self) {
+        self.store.refresh(self.id.inner()).await;
+    }
+
     /// Gets data from the Session's HashMap
     ///
     /// Provides an Option<T> that returns the requested data from the Sessions store.
@@ -212,6 +253,49 @@ where
         self.store.clear_session_data(self.id.inner());
     }
 
+    /// Atomically reads, mutates and writes back a single Key in the Session's HashMap.
+    ///
+    /// The Key's current value (or `V::default()` if it does not yet exist) is deserialized
+    /// and handed to `f` as `&mut V`. The entry lock is held for the duration of the closure,
+    /// so concurrent requests touching the same Key cannot race a `get` against a later `set`.
+    /// The possibly-mutated value is reserialized back once `f` returns.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.update("count", |count: &mut usize| *count += 1);
+    /// ```
+    ///
+    #[inline]
+    pub fn update<V, F>(&self, key: &str, f: F)
+    where
+        V: Serialize + DeserializeOwned + Default,
+        F: FnOnce(&mut V),
+    {
+        self.store.update(self.id.inner(), key, f);
+    }
+
+    /// Atomically hands a mutable reference to the Session's entire data map to `f`.
+    ///
+    /// Like [`update`](Session::update) this holds the entry lock for the duration of the
+    /// closure, but rather than a single deserialized Key it exposes the raw
+    /// `&mut HashMap<String, String>` backing the Session so several Keys can be read and
+    /// written together as one atomic step.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.tap(|data| {
+    ///     data.insert("last_seen".to_string(), "now".to_string());
+    /// });
+    /// ```
+    ///
+    #[inline]
+    pub fn tap<F>(&self, f: F)
+    where
+        F: FnOnce(&mut std::collections::HashMap<String, String>),
+    {
+        self.store.tap(self.id.inner(), f);
+    }
+
     /// Returns a i64 count of how many Sessions exist.
     ///
     /// If the Session is persistant it will return all sessions within the database.
@@ -227,6 +311,55 @@ where
         self.store.count_sessions().await
     }
 
+    /// Gets a nested value out of a Key's stored JSON using a dotted path, e.g.
+    /// `"user.profile.theme"`.
+    ///
+    /// The Key itself must hold a JSON object; each segment of `path` walks one level deeper.
+    /// Returns `None` if the Key does not exist, any segment along `path` is missing, or the
+    /// leaf fails to deserialize into `T`.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let theme: Option<String> = session.get_dot("profile", "user.theme");
+    /// ```
+    ///
+    #[inline]
+    pub fn get_dot<T: serde::de::DeserializeOwned>(&self, key: &str, path: &str) -> Option<T> {
+        self.store.get_dot(self.id.inner(), key, path)
+    }
+
+    /// Sets a nested value inside a Key's stored JSON using a dotted path, e.g.
+    /// `"user.profile.theme"`.
+    ///
+    /// Creates the Key as an empty JSON object if it does not already exist, and creates any
+    /// missing intermediate objects along `path`. Does nothing if a segment along `path`
+    /// already holds a non-object value.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.set_dot("profile", "user.theme", "dark");
+    /// ```
+    ///
+    #[inline]
+    pub fn set_dot(&self, key: &str, path: &str, value: impl Serialize) {
+        self.store.set_dot(self.id.inner(), key, path, value);
+    }
+
+    /// Removes a nested value from a Key's stored JSON using a dotted path, e.g.
+    /// `"user.profile.theme"`.
+    ///
+    /// Does nothing if the Key or any segment along `path` does not exist.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// session.remove_dot("profile", "user.theme");
+    /// ```
+    ///
+    #[inline]
+    pub fn remove_dot(&self, key: &str, path: &str) {
+        self.store.remove_dot(self.id.inner(), key, path);
+    }
+
     /// Returns the SessionID for this Session.
     ///
     /// The SessionID contains the Uuid generated at the beginning of this Session.
@@ -319,4 +452,21 @@ where
     pub async fn count(&self) -> i64 {
         self.store.count_sessions().await
     }
+
+    /// Gets a nested value out of a Key's stored JSON using a dotted path, e.g.
+    /// `"user.profile.theme"`.
+    ///
+    /// The Key itself must hold a JSON object; each segment of `path` walks one level deeper.
+    /// Returns `None` if the Key does not exist, any segment along `path` is missing, or the
+    /// leaf fails to deserialize into `T`.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let theme: Option<String> = session.get_dot("profile", "user.theme");
+    /// ```
+    ///
+    #[inline]
+    pub fn get_dot<T: serde::de::DeserializeOwned>(&self, key: &str, path: &str) -> Option<T> {
+        self.store.get_dot(self.id.inner(), key, path)
+    }
 }
@@ -0,0 +1,249 @@
+use cookie::Key;
+use std::time::Duration;
+
+/// Configuration for how a [`SessionStore`](crate::SessionStore) creates and persists
+/// Sessions.
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub(crate) cookie_name: String,
+    pub(crate) key: Option<Key>,
+    pub(crate) table_name: String,
+    pub(crate) persistence_policy: PersistencePolicy,
+    pub(crate) max_age: Duration,
+    pub(crate) purge_interval: Option<Duration>,
+    pub(crate) refresh_on_access: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "id".into(),
+            key: None,
+            table_name: "async_sessions".into(),
+            persistence_policy: PersistencePolicy::default(),
+            max_age: Duration::from_secs(60 * 60 * 24),
+            purge_interval: None,
+            refresh_on_access: false,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Sets the Session's Database Table Name.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let config = SessionConfig::default().with_table_name("my_table");
+    /// ```
+    ///
+    pub fn with_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    /// Sets the [`PersistencePolicy`] used to decide when a Session is written back to
+    /// the database.
+    ///
+    /// Defaults to [`PersistencePolicy::Always`].
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let config = SessionConfig::default().with_persistence_policy(PersistencePolicy::ChangedOnly);
+    /// ```
+    ///
+    pub fn with_persistence_policy(mut self, policy: PersistencePolicy) -> Self {
+        self.persistence_policy = policy;
+        self
+    }
+
+    /// Sets how long a Session may go untouched before it is considered expired.
+    ///
+    /// Consulted when a Session is first created, and whenever it is refreshed, to compute
+    /// its expiry timestamp; [`SessionStore::cleanup_expired`](crate::SessionStore::cleanup_expired)
+    /// then purges the Sessions whose expiry has passed. Defaults to 24 hours.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let config = SessionConfig::default().with_max_age(Duration::from_secs(60 * 60));
+    /// ```
+    ///
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Opts into a background task that periodically purges expired Sessions.
+    ///
+    /// When set, [`SessionLayer::new`](crate::SessionLayer::new) spawns a task that calls
+    /// [`SessionStore::cleanup_expired`](crate::SessionStore::cleanup_expired) on this
+    /// interval for as long as the process runs. Left unset (the default), no such task is
+    /// spawned and expired rows are only ever removed by an explicit `cleanup_expired` call.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let config = SessionConfig::default().with_purge_interval(Duration::from_secs(60 * 30));
+    /// ```
+    ///
+    pub fn with_purge_interval(mut self, interval: Duration) -> Self {
+        self.purge_interval = Some(interval);
+        self
+    }
+
+    /// Enables sliding expiration: a Session's `expires_at` is bumped forward by `max_age`
+    /// every time it is loaded during a request, rather than only at creation.
+    ///
+    /// This gives "keep me logged in while active" semantics — an active user's Session
+    /// never expires mid-use, but one that sits idle for longer than `max_age` still does.
+    /// Combine with [`Session::refresh`](crate::Session::refresh) to extend a Session's
+    /// expiry outside of this automatic per-request refresh. Defaults to `false`.
+    ///
+    /// # Examples
+    /// ```rust ignore
+    /// let config = SessionConfig::default().with_refresh_on_access(true);
+    /// ```
+    ///
+    pub fn with_refresh_on_access(mut self, refresh_on_access: bool) -> Self {
+        self.refresh_on_access = refresh_on_access;
+        self
+    }
+}
+
+/// Controls when a [`SessionStore`](crate::SessionStore) writes a Session back to the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistencePolicy {
+    /// Always write the Session back at the end of every request, whether or not it changed.
+    Always,
+    /// Only write the Session back if it was marked dirty during the request, i.e. something
+    /// called `set`, `remove`, `clear`, `renew` or `destroy` on it.
+    ChangedOnly,
+    /// Only write the Session back if a row for it already exists in the database, or if it
+    /// was marked dirty during the request. Anonymous visitors who never stored anything
+    /// never get a row created for them.
+    ExistingOnly,
+}
+
+impl Default for PersistencePolicy {
+    fn default() -> Self {
+        PersistencePolicy::Always
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_store::test_support::{store_with_config, NoopPool};
+    use crate::SessionStore;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn store_with_policy(policy: PersistencePolicy) -> SessionStore<NoopPool> {
+        store_with_config(SessionConfig::default().with_persistence_policy(policy))
+    }
+
+    #[tokio::test]
+    async fn always_saves_even_when_clean_and_new() {
+        let store = store_with_policy(PersistencePolicy::Always);
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+        store.set_store(id, true);
+        // `set_store` itself marks the entry dirty; clear it back out so the Session is clean.
+        store.inner.get_mut(&id.to_string()).unwrap().dirty = false;
+
+        assert!(store.save(id).await.is_ok());
+        assert!(store.inner.get(&id.to_string()).unwrap().exists_in_db);
+    }
+
+    #[tokio::test]
+    async fn changed_only_skips_clean_sessions() {
+        let store = store_with_policy(PersistencePolicy::ChangedOnly);
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+        store.set_store(id, true);
+        // `set_store` itself marks the entry dirty; clear it back out to simulate an
+        // untouched Session.
+        store.inner.get_mut(&id.to_string()).unwrap().dirty = false;
+
+        store.save(id).await.unwrap();
+        assert!(!store.inner.get(&id.to_string()).unwrap().exists_in_db);
+
+        store.set(id, "key", "value");
+        store.save(id).await.unwrap();
+        assert!(store.inner.get(&id.to_string()).unwrap().exists_in_db);
+    }
+
+    #[tokio::test]
+    async fn existing_only_skips_untouched_new_sessions() {
+        let store = store_with_policy(PersistencePolicy::ExistingOnly);
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+        store.set_store(id, true);
+        // `set_store` itself marks the entry dirty; clear it back out to simulate a Session
+        // nobody actually stored anything in.
+        store.inner.get_mut(&id.to_string()).unwrap().dirty = false;
+
+        store.save(id).await.unwrap();
+        assert!(!store.inner.get(&id.to_string()).unwrap().exists_in_db);
+    }
+
+    #[tokio::test]
+    async fn existing_only_saves_a_new_session_that_was_written_to() {
+        let store = store_with_policy(PersistencePolicy::ExistingOnly);
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+        store.set_store(id, true);
+        store.set(id, "key", "value");
+
+        store.save(id).await.unwrap();
+        assert!(store.inner.get(&id.to_string()).unwrap().exists_in_db);
+    }
+
+    #[tokio::test]
+    async fn refresh_on_access_bumps_expiry_on_reload() {
+        let store = store_with_config(SessionConfig::default().with_refresh_on_access(true));
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+
+        let first_expiry = store
+            .inner
+            .get(&id.to_string())
+            .unwrap()
+            .expires_at
+            .unwrap();
+        store.inner.get_mut(&id.to_string()).unwrap().expires_at =
+            Some(first_expiry - Duration::from_secs(60));
+
+        store.ensure_entry(id).await;
+        let refreshed_expiry = store
+            .inner
+            .get(&id.to_string())
+            .unwrap()
+            .expires_at
+            .unwrap();
+
+        assert!(refreshed_expiry > first_expiry);
+    }
+
+    #[tokio::test]
+    async fn without_refresh_on_access_expiry_is_untouched_on_reload() {
+        let store = store_with_policy(PersistencePolicy::Always);
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+
+        let first_expiry = store
+            .inner
+            .get(&id.to_string())
+            .unwrap()
+            .expires_at
+            .unwrap();
+
+        store.ensure_entry(id).await;
+        let second_expiry = store
+            .inner
+            .get(&id.to_string())
+            .unwrap()
+            .expires_at
+            .unwrap();
+
+        assert_eq!(first_expiry, second_expiry);
+    }
+}
@@ -0,0 +1,889 @@
+use crate::{DatabasePool, PersistencePolicy, SessionConfig};
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time::SystemTime};
+use uuid::Uuid;
+
+/// The in-memory record a [`SessionStore`] keeps for a single Session, mirrored to the
+/// database according to the configured [`PersistencePolicy`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionEntry {
+    pub(crate) data: HashMap<String, String>,
+    pub(crate) longterm: bool,
+    pub(crate) storable: bool,
+    /// Set whenever the Session's data is mutated during a request; consulted at response
+    /// time by [`SessionStore::session_should_save`] under `PersistencePolicy::ChangedOnly`.
+    pub(crate) dirty: bool,
+    /// Set once a row for this Session is known to have actually been written to the
+    /// database; consulted under `PersistencePolicy::ExistingOnly`. Promoted by
+    /// [`SessionStore::save`] after a successful write.
+    pub(crate) exists_in_db: bool,
+    /// Set by [`SessionStore::destroy`]; tells [`SessionStore::save`] to delete the row and
+    /// drop the entry instead of persisting it, distinguishing "destroy this Session" from
+    /// an ordinary dirty write.
+    pub(crate) pending_destroy: bool,
+    /// Set on the old id's leftover entry by [`SessionStore::rotate_id`] when
+    /// `renew`/`regenerate` move a Session to a new id. Lets code that only has the id a
+    /// Session was extracted under (e.g. a response-time cookie rewrite) find out where it
+    /// ended up, via [`SessionStore::resolve_id`].
+    pub(crate) redirected_to: Option<Uuid>,
+    /// When this Session is considered expired, seeded from `config.max_age` by
+    /// [`SessionStore::ensure_entry`]. `None` for an entry created without one (e.g. a
+    /// redirect tombstone, which is never itself subject to expiry). Consulted by
+    /// [`SessionStore::cleanup_expired`].
+    pub(crate) expires_at: Option<SystemTime>,
+}
+
+/// A Session Store.
+///
+/// Holds the in-memory map of live Sessions along with the Database connection used to
+/// persist them, plus the [`SessionConfig`] that governs cookie naming, table naming and
+/// the Store's [`PersistencePolicy`].
+#[derive(Debug, Clone)]
+pub struct SessionStore<T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    pub(crate) config: SessionConfig,
+    pub(crate) inner: Arc<DashMap<String, SessionEntry>>,
+    pub(crate) client: Option<T>,
+}
+
+impl<T> SessionStore<T>
+where
+    T: DatabasePool + Clone + Debug + Sync + Send + 'static,
+{
+    /// Makes sure an in-memory [`SessionEntry`] exists for `id`, creating one if this is the
+    /// first time this Session is touched during the process's lifetime. A newly created
+    /// entry's `exists_in_db` is seeded from an actual `client.exists` lookup so
+    /// `PersistencePolicy::ExistingOnly` can tell returning visitors from brand new ones.
+    /// Called once from [`Session::new`](crate::Session::new) when a Session is extracted.
+    ///
+    /// If `config.refresh_on_access` is set and the entry already exists, this also bumps
+    /// its `expires_at` forward by `max_age` and writes the new expiry to the database row,
+    /// giving sliding expiration: an actively used Session's expiry keeps retreating, while
+    /// one nobody loads still ages out.
+    pub(crate) async fn ensure_entry(&self, id: Uuid) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            if self.config.refresh_on_access {
+                let new_expiry = SystemTime::now() + self.config.max_age;
+                entry.expires_at = Some(new_expiry);
+                let storable = entry.storable;
+                drop(entry);
+                self.persist_expiry(id, new_expiry, storable).await;
+            }
+            return;
+        }
+
+        let exists_in_db = match &self.client {
+            Some(client) => client
+                .exists(&id.to_string(), &self.config.table_name)
+                .await
+                .unwrap_or(false),
+            None => false,
+        };
+
+        self.inner.insert(
+            id.to_string(),
+            SessionEntry {
+                exists_in_db,
+                expires_at: Some(SystemTime::now() + self.config.max_age),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Gets data from the Session's HashMap.
+    pub(crate) fn get<V: DeserializeOwned>(&self, id: Uuid, key: &str) -> Option<V> {
+        let entry = self.inner.get(&id.to_string())?;
+        let raw = entry.data.get(key)?;
+        serde_json::from_str(raw).ok()
+    }
+
+    /// Removes a Key from the Session's HashMap, returning its deserialized value.
+    pub(crate) fn get_remove<V: DeserializeOwned>(&self, id: Uuid, key: &str) -> Option<V> {
+        let mut entry = self.inner.get_mut(&id.to_string())?;
+        let raw = entry.data.remove(key)?;
+        entry.dirty = true;
+        serde_json::from_str(&raw).ok()
+    }
+
+    /// Sets data on the Session's HashMap.
+    pub(crate) fn set(&self, id: Uuid, key: &str, value: impl Serialize) {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        if let Ok(raw) = serde_json::to_string(&value) {
+            entry.data.insert(key.to_string(), raw);
+            entry.dirty = true;
+        }
+    }
+
+    /// Removes a Key from the Session's HashMap.
+    pub(crate) fn remove(&self, id: Uuid, key: &str) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.data.remove(key);
+            entry.dirty = true;
+        }
+    }
+
+    /// Clears all data from the Session's HashMap.
+    pub(crate) fn clear_session_data(&self, id: Uuid) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.data.clear();
+            entry.dirty = true;
+        }
+    }
+
+    /// Sets whether the Session uses a long term expiration.
+    pub(crate) fn set_longterm(&self, id: Uuid, longterm: bool) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.longterm = longterm;
+            entry.dirty = true;
+        }
+    }
+
+    /// Sets whether the Session is storable.
+    pub(crate) fn set_store(&self, id: Uuid, storable: bool) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.storable = storable;
+            entry.dirty = true;
+        }
+    }
+
+    /// Bumps the Session's `expires_at` forward by `config.max_age` and writes the new expiry
+    /// to the database row, as if it had just been loaded under `config.refresh_on_access`.
+    /// Lets a handler extend a Session's expiry explicitly, independent of that automatic
+    /// per-request refresh.
+    pub(crate) async fn refresh(&self, id: Uuid) {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        let new_expiry = SystemTime::now() + self.config.max_age;
+        entry.expires_at = Some(new_expiry);
+        let storable = entry.storable;
+        drop(entry);
+
+        self.persist_expiry(id, new_expiry, storable).await;
+    }
+
+    /// Writes `expires_at` to the database row for `id`, if the Session is `storable` and a
+    /// database is configured. Mirrors the `storable` gate every other database write in this
+    /// Store already applies (see [`save`](SessionStore::save)). Shared by the sliding-expiry
+    /// bump in [`ensure_entry`](SessionStore::ensure_entry) and the explicit
+    /// [`refresh`](SessionStore::refresh).
+    async fn persist_expiry(&self, id: Uuid, expires_at: SystemTime, storable: bool) {
+        if !storable {
+            return;
+        }
+
+        let Some(client) = &self.client else {
+            return;
+        };
+
+        let _ = client
+            .update_expiry(
+                &id.to_string(),
+                &self.config.table_name,
+                unix_timestamp(expires_at),
+            )
+            .await;
+    }
+
+    /// Returns a count of how many Sessions currently exist.
+    ///
+    /// Queries the database for a Store backed by one, since the in-memory map only ever
+    /// holds Sessions this process has itself touched; falls back to the in-memory count for
+    /// a Store with no database configured.
+    pub(crate) async fn count_sessions(&self) -> i64 {
+        match &self.client {
+            Some(client) => client
+                .count_sessions(&self.config.table_name)
+                .await
+                .unwrap_or(0),
+            None => self.inner.len() as i64,
+        }
+    }
+
+    /// Allocates a fresh Session id, guaranteed not to collide with one already live in memory
+    /// or already present in the database. Shared by [`rotate_id`](SessionStore::rotate_id) and
+    /// [`Session::generate_uuid`](crate::Session::generate_uuid), the two places a new id is
+    /// minted for a Session.
+    pub(crate) async fn generate_unique_id(&self) -> Uuid {
+        loop {
+            let token = Uuid::new_v4();
+
+            if self.inner.contains_key(&token.to_string()) {
+                continue;
+            }
+
+            let Some(client) = &self.client else {
+                return token;
+            };
+
+            // Unwrap should be safe to use as we would want it to crash if there was a major
+            // database error. This would mean the database no longer is online or the table
+            // missing etc.
+            if !client
+                .exists(&token.to_string(), &self.config.table_name)
+                .await
+                .unwrap()
+            {
+                return token;
+            }
+        }
+    }
+
+    /// Moves a Session's data from `old_id` to a freshly allocated `new_id`, deleting the old
+    /// database row for a `storable` Session and leaving a redirect behind at `old_id` so
+    /// [`resolve_id`](SessionStore::resolve_id) can later find where it went. Shared by
+    /// [`renew`](SessionStore::renew) (`keep_data: false`, discarding the old data) and
+    /// `regenerate` (`keep_data: true`, carrying it across).
+    async fn rotate_id(&self, old_id: Uuid, keep_data: bool) -> Uuid {
+        let new_id = self.generate_unique_id().await;
+
+        let Some((_, mut entry)) = self.inner.remove(&old_id.to_string()) else {
+            return new_id;
+        };
+
+        let storable = entry.storable;
+        if !keep_data {
+            entry.data.clear();
+        }
+        entry.dirty = true;
+        entry.exists_in_db = false;
+        entry.pending_destroy = false;
+        entry.redirected_to = None;
+
+        self.inner.insert(new_id.to_string(), entry);
+        self.inner.insert(
+            old_id.to_string(),
+            SessionEntry {
+                redirected_to: Some(new_id),
+                ..Default::default()
+            },
+        );
+
+        if storable {
+            if let Some(client) = &self.client {
+                let _ = client
+                    .delete_one_by_id(&old_id.to_string(), &self.config.table_name)
+                    .await;
+            }
+        }
+
+        new_id
+    }
+
+    /// Renews a Session's ID, discarding the old key's data, and returns the new id.
+    pub(crate) async fn renew(&self, old_id: Uuid) -> Uuid {
+        self.rotate_id(old_id, false).await
+    }
+
+    /// Rotates a Session's ID while keeping its data intact, and returns the new id. The
+    /// standard defense against session fixation.
+    pub(crate) async fn regenerate(&self, old_id: Uuid) -> Uuid {
+        self.rotate_id(old_id, true).await
+    }
+
+    /// Flags the Session to be destroyed: its in-memory data is cleared immediately, and
+    /// [`save`](SessionStore::save) deletes its database row (if any) and drops the entry
+    /// instead of persisting it at the end of the request.
+    pub(crate) fn destroy(&self, id: Uuid) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.data.clear();
+            entry.dirty = true;
+            entry.pending_destroy = true;
+        }
+    }
+
+    /// Follows the chain of [`SessionEntry::redirected_to`] tombstones left by
+    /// [`rotate_id`](SessionStore::rotate_id), starting from `id`, and returns the final id a
+    /// Session ended up under. Returns `id` unchanged if it was never redirected.
+    pub fn resolve_id(&self, id: Uuid) -> Uuid {
+        let mut current = id;
+
+        for _ in 0..8 {
+            let Some(entry) = self.inner.get(&current.to_string()) else {
+                break;
+            };
+            let Some(next) = entry.redirected_to else {
+                break;
+            };
+            drop(entry);
+            current = next;
+        }
+
+        current
+    }
+
+    /// Removes the redirect tombstone left at `id` by [`rotate_id`](SessionStore::rotate_id),
+    /// once whoever needed [`resolve_id`](SessionStore::resolve_id) has finished with it.
+    pub fn evict_redirect(&self, id: Uuid) {
+        let Some(entry) = self.inner.get(&id.to_string()) else {
+            return;
+        };
+
+        if entry.redirected_to.is_some() {
+            drop(entry);
+            self.inner.remove(&id.to_string());
+        }
+    }
+
+    /// Returns the Session's current `expires_at`, if it has one. Used by
+    /// [`SessionLayer`](crate::SessionLayer) to set the `Set-Cookie` header's `Max-Age` so the
+    /// cookie's lifetime on the client tracks the server-side expiry it was resolved with.
+    pub fn expires_at(&self, id: Uuid) -> Option<SystemTime> {
+        self.inner.get(&id.to_string())?.expires_at
+    }
+
+    /// Atomically reads, mutates and writes back a single Key in the Session's HashMap.
+    ///
+    /// Holds the entry's lock for the duration of `f`: the Key's current value (or
+    /// `V::default()` if it does not yet exist) is deserialized and handed to `f` as
+    /// `&mut V`, then reserialized back into the entry once `f` returns. No other call
+    /// against this Session's entry can interleave a `get`/`set` in between, which is what
+    /// makes this safe against the read-modify-write race a bare `get` + `set` pair has.
+    pub(crate) fn update<V, F>(&self, id: Uuid, key: &str, f: F)
+    where
+        V: Serialize + DeserializeOwned + Default,
+        F: FnOnce(&mut V),
+    {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        let mut value: V = entry
+            .data
+            .get(key)
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default();
+
+        f(&mut value);
+
+        if let Ok(raw) = serde_json::to_string(&value) {
+            entry.data.insert(key.to_string(), raw);
+            entry.dirty = true;
+        }
+    }
+
+    /// Atomically hands a mutable reference to the Session's entire data map to `f`.
+    ///
+    /// Like [`update`](SessionStore::update), holds the entry's lock for the duration of
+    /// `f` so several Keys can be read and written together as one atomic step rather than
+    /// across separate store calls.
+    pub(crate) fn tap<F>(&self, id: Uuid, f: F)
+    where
+        F: FnOnce(&mut HashMap<String, String>),
+    {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        f(&mut entry.data);
+        entry.dirty = true;
+    }
+
+    /// Looks up `key` as a `serde_json::Value` and walks `path` (dot-separated object field
+    /// names) to the leaf, deserializing it into `V`.
+    pub(crate) fn get_dot<V: DeserializeOwned>(
+        &self,
+        id: Uuid,
+        key: &str,
+        path: &str,
+    ) -> Option<V> {
+        let entry = self.inner.get(&id.to_string())?;
+        let raw = entry.data.get(key)?;
+        let root: Value = serde_json::from_str(raw).ok()?;
+
+        let mut current = &root;
+        for segment in path.split('.') {
+            current = current.get(segment)?;
+        }
+
+        serde_json::from_value(current.clone()).ok()
+    }
+
+    /// Walks `path` the same way [`get_dot`](SessionStore::get_dot) does, creating any
+    /// missing intermediate objects along the way, and overwrites the leaf with `value`.
+    /// Creates `key` as an empty JSON object first if it does not already exist.
+    pub(crate) fn set_dot(&self, id: Uuid, key: &str, path: &str, value: impl Serialize) {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        let mut root: Value = entry
+            .data
+            .get(key)
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut current = &mut root;
+        for segment in &segments[..segments.len().saturating_sub(1)] {
+            let Some(obj) = current.as_object_mut() else {
+                // A segment along `path` already holds a non-object value (e.g. the stored
+                // Key was a plain string) — bail out without writing rather than panic.
+                return;
+            };
+            current = obj
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Default::default()));
+        }
+
+        let Some(obj) = current.as_object_mut() else {
+            // The leaf's parent is not an object either; leave the stored Key untouched.
+            return;
+        };
+        if let (Some(leaf), Ok(value)) = (segments.last(), serde_json::to_value(value)) {
+            obj.insert(leaf.to_string(), value);
+        }
+
+        if let Ok(raw) = serde_json::to_string(&root) {
+            entry.data.insert(key.to_string(), raw);
+            entry.dirty = true;
+        }
+    }
+
+    /// Walks `path` the same way [`get_dot`](SessionStore::get_dot) does and removes the
+    /// leaf field from its parent object. Does nothing if `key` or any segment of `path`
+    /// does not exist.
+    pub(crate) fn remove_dot(&self, id: Uuid, key: &str, path: &str) {
+        let Some(mut entry) = self.inner.get_mut(&id.to_string()) else {
+            return;
+        };
+
+        let Some(raw) = entry.data.get(key) else {
+            return;
+        };
+        let Ok(mut root) = serde_json::from_str::<Value>(raw) else {
+            return;
+        };
+
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut current = &mut root;
+        for segment in &segments[..segments.len().saturating_sub(1)] {
+            let Some(next) = current.get_mut(segment) else {
+                return;
+            };
+            current = next;
+        }
+
+        let removed = match (segments.last(), current.as_object_mut()) {
+            (Some(leaf), Some(obj)) => obj.remove(*leaf).is_some(),
+            _ => false,
+        };
+        if !removed {
+            return;
+        }
+
+        if let Ok(raw) = serde_json::to_string(&root) {
+            entry.data.insert(key.to_string(), raw);
+            entry.dirty = true;
+        }
+    }
+
+    /// Consults `config.persistence_policy` to decide whether the Session identified by `id`
+    /// should be written back to the database at the end of the request.
+    ///
+    /// `ChangedOnly` skips the write when nothing was mutated during the request.
+    /// `ExistingOnly` skips the write for Sessions that have never had a row persisted for
+    /// them *and* weren't touched during this request either, so anonymous visitors who
+    /// never stored anything never create one — while a Session that stores data for the
+    /// first time (e.g. right at login) still gets its row created.
+    fn session_should_save(&self, id: Uuid) -> bool {
+        let Some(entry) = self.inner.get(&id.to_string()) else {
+            return false;
+        };
+
+        match self.config.persistence_policy {
+            PersistencePolicy::Always => true,
+            PersistencePolicy::ChangedOnly => entry.dirty,
+            PersistencePolicy::ExistingOnly => entry.exists_in_db || entry.dirty,
+        }
+    }
+
+    /// Marks a Session's row as confirmed-written, so later calls can tell it apart from a
+    /// Session that only exists in memory. Called by [`save`](SessionStore::save) right
+    /// after a successful database write.
+    fn mark_persisted(&self, id: Uuid) {
+        if let Some(mut entry) = self.inner.get_mut(&id.to_string()) {
+            entry.exists_in_db = true;
+            entry.dirty = false;
+        }
+    }
+
+    /// Persists the Session identified by `id` to the database, the real counterpart to the
+    /// mutating methods above which only ever touch the in-memory entry.
+    ///
+    /// If the Session was flagged via [`destroy`](SessionStore::destroy), its row is deleted
+    /// and the entry dropped instead. Otherwise the write only happens if both
+    /// `entry.storable` and [`session_should_save`](SessionStore::session_should_save) hold;
+    /// on success the entry is marked persisted via
+    /// [`mark_persisted`](SessionStore::mark_persisted). The write carries the entry's current
+    /// `expires_at` alongside its data, so [`cleanup_expired`](SessionStore::cleanup_expired)
+    /// has a column to scan against even for a Session that was never explicitly refreshed.
+    /// Intended to be called once per request, after the handler has run, by whatever installs
+    /// the Session into the request (the Session layer).
+    pub async fn save(&self, id: Uuid) -> Result<(), T::Error> {
+        let id_str = id.to_string();
+
+        let Some(entry) = self.inner.get(&id_str) else {
+            return Ok(());
+        };
+
+        if entry.pending_destroy {
+            let storable = entry.storable;
+            drop(entry);
+
+            if storable {
+                if let Some(client) = &self.client {
+                    client
+                        .delete_one_by_id(&id_str, &self.config.table_name)
+                        .await?;
+                }
+            }
+
+            self.inner.remove(&id_str);
+            return Ok(());
+        }
+
+        let storable = entry.storable;
+        drop(entry);
+
+        if !storable || !self.session_should_save(id) {
+            return Ok(());
+        }
+
+        if let Some(client) = &self.client {
+            let (data, expires_at) = self
+                .inner
+                .get(&id_str)
+                .map(|entry| {
+                    (
+                        serde_json::to_string(&entry.data).unwrap_or_default(),
+                        entry.expires_at.unwrap_or_else(SystemTime::now),
+                    )
+                })
+                .unwrap_or_else(|| (String::new(), SystemTime::now()));
+
+            client
+                .store_session(
+                    &id_str,
+                    &self.config.table_name,
+                    &data,
+                    unix_timestamp(expires_at),
+                )
+                .await?;
+        }
+
+        self.mark_persisted(id);
+        Ok(())
+    }
+
+    /// Purges Sessions whose expiry timestamp has passed.
+    ///
+    /// Issues a single bulk delete against `config.table_name` for every database row whose
+    /// stored expiry is at or before now, via [`DatabasePool::delete_expired`], then prunes
+    /// the same expired entries out of the in-memory map. Called directly, or periodically by
+    /// the background task [`SessionLayer::new`](crate::SessionLayer::new) spawns when
+    /// [`SessionConfig::with_purge_interval`] is set.
+    pub async fn cleanup_expired(&self) -> Result<(), T::Error> {
+        let now = SystemTime::now();
+
+        if let Some(client) = &self.client {
+            client
+                .delete_expired(&self.config.table_name, unix_timestamp(now))
+                .await?;
+        }
+
+        self.inner
+            .retain(|_, entry| !matches!(entry.expires_at, Some(expires_at) if expires_at <= now));
+
+        Ok(())
+    }
+}
+
+/// Converts a [`SystemTime`] to whole seconds since the Unix epoch, for the expiry columns
+/// [`DatabasePool::update_expiry`] and [`DatabasePool::delete_expired`] deal in. Clamped to
+/// `0` for a time before the epoch, which in practice cannot happen here.
+fn unix_timestamp(time: SystemTime) -> i64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Shared test fixtures for this crate's `SessionStore`-backed unit tests.
+///
+/// Exists so `session_store.rs` and `session_config.rs` don't each paste their own copy of
+/// `NoopPool`'s `DatabasePool` impl and the bare `SessionStore` constructors it backs; both
+/// modules' test suites import from here instead.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::SessionConfig;
+    use async_trait::async_trait;
+
+    #[derive(Debug, Clone)]
+    pub(crate) struct NoopPool;
+
+    #[async_trait]
+    impl DatabasePool for NoopPool {
+        type Error = std::convert::Infallible;
+
+        async fn exists(&self, _id: &str, _table_name: &str) -> Result<bool, Self::Error> {
+            Ok(false)
+        }
+
+        async fn delete_one_by_id(&self, _id: &str, _table_name: &str) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn store_session(
+            &self,
+            _id: &str,
+            _table_name: &str,
+            _data: &str,
+            _expires_at: i64,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn count_sessions(&self, _table_name: &str) -> Result<i64, Self::Error> {
+            Ok(0)
+        }
+
+        async fn delete_expired(
+            &self,
+            _table_name: &str,
+            _expires_before: i64,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn update_expiry(
+            &self,
+            _id: &str,
+            _table_name: &str,
+            _expires_at: i64,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// A bare `SessionStore<NoopPool>` with default config and no database attached.
+    pub(crate) fn store() -> SessionStore<NoopPool> {
+        store_with_config(SessionConfig::default())
+    }
+
+    /// A bare `SessionStore<NoopPool>` built from a caller-supplied `config`, for tests that
+    /// need to exercise a non-default setting (e.g. a `PersistencePolicy` or
+    /// `refresh_on_access`).
+    pub(crate) fn store_with_config(config: SessionConfig) -> SessionStore<NoopPool> {
+        SessionStore {
+            config,
+            inner: Arc::new(DashMap::new()),
+            client: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use test_support::store;
+
+    fn seed(store: &SessionStore<test_support::NoopPool>, id: Uuid, key: &str, value: Value) {
+        store.inner.insert(
+            id.to_string(),
+            SessionEntry {
+                data: HashMap::from([(key.to_string(), value.to_string())]),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn get_dot_returns_none_for_missing_segment() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(
+            &store,
+            id,
+            "profile",
+            serde_json::json!({"user": {"theme": "dark"}}),
+        );
+
+        let missing: Option<String> = store.get_dot(id, "profile", "user.nickname");
+        assert_eq!(missing, None);
+
+        let theme: Option<String> = store.get_dot(id, "profile", "user.theme");
+        assert_eq!(theme, Some("dark".to_string()));
+    }
+
+    #[test]
+    fn get_dot_returns_none_for_missing_key() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(&store, id, "profile", serde_json::json!({}));
+
+        let missing: Option<String> = store.get_dot(id, "does-not-exist", "user.theme");
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn set_dot_bails_out_on_non_object_segment() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(
+            &store,
+            id,
+            "profile",
+            serde_json::json!({"user": "a plain string"}),
+        );
+
+        // "user" is a string, not an object, so walking into "user.theme" can't create an
+        // intermediate object there — this must bail out rather than panic.
+        store.set_dot(id, "profile", "user.theme", "dark");
+
+        let user: Option<Value> = store.get_dot(id, "profile", "user");
+        assert_eq!(user, Some(Value::String("a plain string".into())));
+    }
+
+    #[test]
+    fn set_dot_creates_missing_intermediate_objects() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(&store, id, "profile", serde_json::json!({}));
+
+        store.set_dot(id, "profile", "user.theme", "dark");
+
+        let theme: Option<String> = store.get_dot(id, "profile", "user.theme");
+        assert_eq!(theme, Some("dark".to_string()));
+    }
+
+    #[test]
+    fn remove_dot_removes_leaf_under_empty_key() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(
+            &store,
+            id,
+            "",
+            serde_json::json!({"user": {"theme": "dark"}}),
+        );
+
+        store.remove_dot(id, "", "user.theme");
+
+        let theme: Option<String> = store.get_dot(id, "", "user.theme");
+        assert_eq!(theme, None);
+    }
+
+    #[tokio::test]
+    async fn cleanup_expired_evicts_only_expired_entries() {
+        let store = store();
+        let expired = Uuid::new_v4();
+        let live = Uuid::new_v4();
+
+        store.inner.insert(
+            expired.to_string(),
+            SessionEntry {
+                expires_at: Some(SystemTime::now() - Duration::from_secs(1)),
+                ..Default::default()
+            },
+        );
+        store.inner.insert(
+            live.to_string(),
+            SessionEntry {
+                expires_at: Some(SystemTime::now() + Duration::from_secs(60)),
+                ..Default::default()
+            },
+        );
+
+        store.cleanup_expired().await.unwrap();
+
+        assert!(!store.inner.contains_key(&expired.to_string()));
+        assert!(store.inner.contains_key(&live.to_string()));
+    }
+
+    #[tokio::test]
+    async fn cleanup_expired_leaves_entries_without_an_expiry() {
+        let store = store();
+        let id = Uuid::new_v4();
+        seed(&store, id, "profile", serde_json::json!({}));
+
+        store.cleanup_expired().await.unwrap();
+
+        assert!(store.inner.contains_key(&id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn regenerate_preserves_data_through_the_full_redirect_cycle() {
+        // Mirrors the real request flow: `Session::new` calls `ensure_entry` before a handler
+        // ever runs, the handler calls `regenerate`, and `SessionService::call` resolves the
+        // redirect, saves at the resolved id, then evicts the tombstone.
+        let store = store();
+        let old_id = Uuid::new_v4();
+        store.ensure_entry(old_id).await;
+        store.set_store(old_id, true);
+        store.set(old_id, "count", 42usize);
+
+        let new_id = store.regenerate(old_id).await;
+        assert_ne!(new_id, old_id);
+
+        let resolved = store.resolve_id(old_id);
+        assert_eq!(resolved, new_id);
+
+        store.save(resolved).await.unwrap();
+        store.evict_redirect(old_id);
+
+        let count: Option<usize> = store.get(new_id, "count");
+        assert_eq!(count, Some(42));
+        assert!(!store.inner.contains_key(&old_id.to_string()));
+        assert!(store.inner.get(&new_id.to_string()).unwrap().exists_in_db);
+    }
+
+    #[tokio::test]
+    async fn generate_unique_id_avoids_ids_already_live_in_memory() {
+        let store = store();
+        let taken = Uuid::new_v4();
+        store
+            .inner
+            .insert(taken.to_string(), SessionEntry::default());
+
+        let generated = store.generate_unique_id().await;
+
+        assert_ne!(generated, taken);
+    }
+
+    #[tokio::test]
+    async fn rotate_id_never_reuses_the_old_id() {
+        let store = store();
+        let old_id = Uuid::new_v4();
+        store.ensure_entry(old_id).await;
+
+        let new_id = store.renew(old_id).await;
+
+        assert_ne!(new_id, old_id);
+    }
+
+    #[tokio::test]
+    async fn expires_at_returns_none_for_an_unknown_id() {
+        let store = store();
+
+        assert_eq!(store.expires_at(Uuid::new_v4()), None);
+    }
+
+    #[tokio::test]
+    async fn expires_at_returns_the_entrys_expiry() {
+        let store = store();
+        let id = Uuid::new_v4();
+        store.ensure_entry(id).await;
+
+        assert!(store.expires_at(id).is_some());
+    }
+}